@@ -1,5 +1,6 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
+use regex::Regex;
 
 #[derive(Serialize, Deserialize)]
 pub struct Message {
@@ -12,6 +13,16 @@ pub struct Message {
 pub struct ClusterBucket {
     pub label: String,
     pub count: usize,
+    /// Count after collapsing near-duplicate messages (see [`group_duplicates`]).
+    /// Equal to `count` when every member is distinct, or when the caller did
+    /// not enable the `dedupe` pass (duplicates are then not collapsed).
+    pub unique_count: usize,
+    /// Mean lexicon-based sentiment across members, clamped to [-1, 1].
+    pub sentiment_score: f64,
+    pub positive_count: usize,
+    pub negative_count: usize,
+    /// Members whose text matched the small profanity/slur term set.
+    pub toxic_count: usize,
     pub sample_messages: Vec<String>,
 }
 
@@ -21,6 +32,28 @@ pub struct ClusterResult {
     pub processed_count: usize,
 }
 
+/// A single moderator-defined classification rule for [`cluster_messages`].
+///
+/// A message matches the rule if it contains any of `keywords` (case
+/// insensitive) or matches `regex`, when present. Rules with a higher
+/// `priority` are evaluated first.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ClusterRule {
+    pub label: String,
+    pub keywords: Vec<String>,
+    pub regex: Option<String>,
+    pub priority: i32,
+}
+
+/// A set of custom [`ClusterRule`]s passed to [`cluster_messages`] in place
+/// of the built-in v0 taxonomy.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ClusterConfig {
+    pub rules: Vec<ClusterRule>,
+    #[serde(default)]
+    pub multi_label: bool,
+}
+
 /// Clusters chat messages into labeled buckets (Questions, Issues, Requests, General Chat).
 ///
 /// # Input JSON Shape
@@ -44,6 +77,11 @@ pub struct ClusterResult {
 ///     {
 ///       "label": "Questions",
 ///       "count": 5,
+///       "unique_count": 5,
+///       "sentiment_score": 0.12,
+///       "positive_count": 2,
+///       "negative_count": 0,
+///       "toxic_count": 0,
 ///       "sample_messages": ["How do I...", "What is...", "Why does..."]
 ///     }
 ///   ],
@@ -59,13 +97,66 @@ pub struct ClusterResult {
 /// - **General Chat**: Everything else
 ///
 /// Returns up to 3 sample messages per bucket.
+///
+/// # Custom Rules
+///
+/// An optional second argument carries a `ClusterConfig` describing
+/// moderator-defined categories instead of the built-in four. See
+/// `ClusterRule`/`ClusterConfig` for the shape. When omitted (`undefined`
+/// or `null`), the v0 behavior above is unchanged.
+///
+/// An optional third argument, `dedupe`, controls whether each bucket's
+/// `unique_count` is computed via the MinHash near-duplicate pass (see
+/// `group_duplicates`). That pass is O(n^2) in a bucket's member count, so
+/// it defaults to `false`/omitted (`unique_count` then just equals `count`).
+/// Pass `true` to opt in for smaller buckets, or call `detect_spam`
+/// separately for a full duplicate report.
+///
+/// An optional fourth argument, `lexicon_json`, overrides the word-polarity
+/// table driving each bucket's `sentiment_score`/`positive_count`/
+/// `negative_count` fields (same `{ "term": weight, ... }` shape as
+/// `score_sentiment`'s override). When omitted, the small embedded lexicon
+/// is used. `toxic_count` always comes from the fixed toxic term set and is
+/// not affected by the lexicon.
 #[wasm_bindgen]
-pub fn cluster_messages(messages_json: JsValue) -> Result<JsValue, JsValue> {
+pub fn cluster_messages(
+    messages_json: JsValue,
+    config_json: JsValue,
+    dedupe: Option<bool>,
+    lexicon_json: JsValue,
+) -> Result<JsValue, JsValue> {
     // Parse incoming messages
     let messages: Vec<Message> = serde_wasm_bindgen::from_value(messages_json)
         .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    // Simple keyword-based clustering (v0 implementation)
+    let config: Option<ClusterConfig> = if config_json.is_undefined() || config_json.is_null() {
+        None
+    } else {
+        Some(
+            serde_wasm_bindgen::from_value(config_json)
+                .map_err(|e| JsValue::from_str(&format!("Config parse error: {}", e)))?,
+        )
+    };
+    let compute_dedup = dedupe.unwrap_or(false);
+    let lexicon: Lexicon = if lexicon_json.is_undefined() || lexicon_json.is_null() {
+        default_lexicon()
+    } else {
+        serde_wasm_bindgen::from_value(lexicon_json)
+            .map_err(|e| JsValue::from_str(&format!("Lexicon parse error: {}", e)))?
+    };
+
+    let result = match config {
+        Some(config) => cluster_messages_with_rules(&messages, &config, compute_dedup, &lexicon)
+            .map_err(|e| JsValue::from_str(&format!("Rule error: {}", e)))?,
+        None => cluster_messages_default(&messages, compute_dedup, &lexicon),
+    };
+
+    serde_wasm_bindgen::to_value(&result)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// The built-in v0 keyword buckets (Questions, Issues/Bugs, Requests, General Chat).
+fn cluster_messages_default(messages: &[Message], compute_dedup: bool, lexicon: &Lexicon) -> ClusterResult {
     let mut questions = Vec::new();
     let mut issues = Vec::new();
     let mut requests = Vec::new();
@@ -73,7 +164,7 @@ pub fn cluster_messages(messages_json: JsValue) -> Result<JsValue, JsValue> {
 
     for msg in messages.iter() {
         let text_lower = msg.text.to_lowercase();
-        
+
         if text_lower.contains('?') || text_lower.contains("how ") || text_lower.contains("what ") || text_lower.contains("why ") {
             questions.push(msg.text.clone());
         } else if text_lower.contains("bug") || text_lower.contains("error") || text_lower.contains("broken") || text_lower.contains("issue") {
@@ -88,46 +179,774 @@ pub fn cluster_messages(messages_json: JsValue) -> Result<JsValue, JsValue> {
     let mut buckets = Vec::new();
 
     if !questions.is_empty() {
-        buckets.push(ClusterBucket {
-            label: "Questions".to_string(),
-            count: questions.len(),
-            sample_messages: questions.into_iter().take(3).collect(),
-        });
+        buckets.push(build_bucket("Questions".to_string(), questions, compute_dedup, lexicon));
     }
 
     if !issues.is_empty() {
-        buckets.push(ClusterBucket {
-            label: "Issues/Bugs".to_string(),
-            count: issues.len(),
-            sample_messages: issues.into_iter().take(3).collect(),
-        });
+        buckets.push(build_bucket("Issues/Bugs".to_string(), issues, compute_dedup, lexicon));
     }
 
     if !requests.is_empty() {
-        buckets.push(ClusterBucket {
-            label: "Requests".to_string(),
-            count: requests.len(),
-            sample_messages: requests.into_iter().take(3).collect(),
-        });
+        buckets.push(build_bucket("Requests".to_string(), requests, compute_dedup, lexicon));
     }
 
     if !general.is_empty() {
-        buckets.push(ClusterBucket {
-            label: "General Chat".to_string(),
-            count: general.len(),
-            sample_messages: general.into_iter().take(3).collect(),
-        });
+        buckets.push(build_bucket("General Chat".to_string(), general, compute_dedup, lexicon));
+    }
+
+    ClusterResult {
+        buckets,
+        processed_count: messages.len(),
+    }
+}
+
+/// Classifies messages against moderator-supplied `ClusterRule`s instead of
+/// the fixed v0 taxonomy.
+///
+/// Rules are evaluated in descending `priority` order. When
+/// `config.multi_label` is `false`, the first matching rule wins and a
+/// message contributes to at most one bucket; when `true`, a message is
+/// added to every rule it matches. `processed_count` always reflects the
+/// raw message total, regardless of how many buckets a message lands in.
+fn cluster_messages_with_rules(
+    messages: &[Message],
+    config: &ClusterConfig,
+    compute_dedup: bool,
+    lexicon: &Lexicon,
+) -> Result<ClusterResult, String> {
+    let mut rules: Vec<&ClusterRule> = config.rules.iter().collect();
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+
+    let compiled: Vec<(&ClusterRule, Option<Regex>)> = rules
+        .into_iter()
+        .map(|rule| {
+            let regex = rule
+                .regex
+                .as_ref()
+                .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid regex in rule '{}': {}", rule.label, e)))
+                .transpose()?;
+            Ok((rule, regex))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut bucket_messages: Vec<Vec<String>> = vec![Vec::new(); compiled.len()];
+
+    for msg in messages.iter() {
+        let text_lower = msg.text.to_lowercase();
+
+        for (idx, (rule, regex)) in compiled.iter().enumerate() {
+            let keyword_match = rule
+                .keywords
+                .iter()
+                .any(|keyword| text_lower.contains(&keyword.to_lowercase()));
+            // Matched against the original text (not text_lower) so
+            // case-sensitive patterns, e.g. a shouting detector like
+            // `^[A-Z\s]+$`, behave as the moderator wrote them.
+            let regex_match = regex.as_ref().is_some_and(|re| re.is_match(&msg.text));
+
+            if keyword_match || regex_match {
+                bucket_messages[idx].push(msg.text.clone());
+                if !config.multi_label {
+                    break;
+                }
+            }
+        }
     }
 
-    let result = ClusterResult {
+    let buckets = compiled
+        .iter()
+        .zip(bucket_messages)
+        .filter(|(_, texts)| !texts.is_empty())
+        .map(|((rule, _), texts)| build_bucket(rule.label.clone(), texts, compute_dedup, lexicon))
+        .collect();
+
+    Ok(ClusterResult {
         buckets,
         processed_count: messages.len(),
+    })
+}
+
+/// Small stopword list used to keep TF-IDF vectors focused on topical terms.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "the", "is", "it", "to", "of", "and", "in", "on", "for", "this", "that", "i",
+    "you", "we", "with", "at", "be", "are", "was", "were", "do", "does", "did", "have", "has",
+    "had", "my", "your", "our", "me", "us", "so", "but", "or", "if", "not", "no", "yes", "as",
+];
+
+/// Lowercases, strips punctuation, and drops stopwords/empties from a message's text.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|w| !STOPWORDS.contains(w))
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// A sparse, L2-normalized TF-IDF vector keyed by term.
+type SparseVector = std::collections::HashMap<String, f64>;
+
+fn l2_normalize(mut vector: SparseVector) -> SparseVector {
+    let norm: f64 = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        for weight in vector.values_mut() {
+            *weight /= norm;
+        }
+    }
+    vector
+}
+
+fn dot(a: &SparseVector, b: &SparseVector) -> f64 {
+    // Iterate the smaller map for a cheap speedup; correctness doesn't depend on it.
+    let (smaller, larger) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    smaller
+        .iter()
+        .filter_map(|(term, weight)| larger.get(term).map(|other| weight * other))
+        .sum()
+}
+
+fn norm(vector: &SparseVector) -> f64 {
+    vector.values().map(|w| w * w).sum::<f64>().sqrt()
+}
+
+/// Cosine similarity between a unit-normalized message vector and a (possibly
+/// un-normalized) cluster sum vector. Cosine similarity is scale-invariant, so
+/// the cluster's running sum can be compared directly without dividing by its
+/// member count first.
+fn cosine_similarity(message_vector: &SparseVector, cluster_sum: &SparseVector) -> f64 {
+    let cluster_norm = norm(cluster_sum);
+    if cluster_norm == 0.0 {
+        return 0.0;
+    }
+    dot(message_vector, cluster_sum) / cluster_norm
+}
+
+struct SemanticCluster {
+    sum_vector: SparseVector,
+    member_indices: Vec<usize>,
+}
+
+/// Clusters chat messages by TF-IDF cosine similarity instead of fixed keyword
+/// buckets, so topics are discovered from the message content itself.
+///
+/// # Algorithm
+///
+/// 1. Tokenize each message (lowercase, strip punctuation, drop stopwords).
+/// 2. Compute document frequency `df[t]` and `idf[t] = ln(N / (1 + df[t]))`.
+/// 3. Build a sparse `tf * idf` vector per message, normalized to unit L2 length.
+/// 4. Greedily assign each message to the most similar existing cluster
+///    (cosine similarity >= `threshold`, default 0.3), or start a new one.
+///
+/// Messages with empty/whitespace-only text, or whose vector has zero norm
+/// (e.g. every token is a stopword), land in a catch-all "Uncategorized" bucket.
+/// Each resulting bucket is labeled by its top-3 terms by summed weight.
+///
+/// An optional third argument, `dedupe`, controls whether each bucket's
+/// `unique_count` is computed via the MinHash near-duplicate pass; see
+/// `cluster_messages` for why it defaults to `false`/omitted.
+///
+/// An optional fourth argument, `lexicon_json`, overrides the word-polarity
+/// table behind each bucket's sentiment fields (not `toxic_count`); see
+/// `cluster_messages` for the shape.
+#[wasm_bindgen]
+pub fn cluster_messages_semantic(
+    messages_json: JsValue,
+    threshold: Option<f64>,
+    dedupe: Option<bool>,
+    lexicon_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let messages: Vec<Message> = serde_wasm_bindgen::from_value(messages_json)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    let lexicon: Lexicon = if lexicon_json.is_undefined() || lexicon_json.is_null() {
+        default_lexicon()
+    } else {
+        serde_wasm_bindgen::from_value(lexicon_json)
+            .map_err(|e| JsValue::from_str(&format!("Lexicon parse error: {}", e)))?
     };
 
+    let result = cluster_messages_semantic_internal(
+        &messages,
+        threshold.unwrap_or(0.3),
+        dedupe.unwrap_or(false),
+        &lexicon,
+    );
+
+    serde_wasm_bindgen::to_value(&result)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+fn cluster_messages_semantic_internal(
+    messages: &[Message],
+    threshold: f64,
+    compute_dedup: bool,
+    lexicon: &Lexicon,
+) -> ClusterResult {
+    let n = messages.len();
+
+    let tokens_per_message: Vec<Vec<String>> = messages.iter().map(|m| tokenize(&m.text)).collect();
+
+    let mut df: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for tokens in &tokens_per_message {
+        let unique: std::collections::HashSet<&String> = tokens.iter().collect();
+        for term in unique {
+            *df.entry(term.clone()).or_insert(0) += 1;
+        }
+    }
+    let idf = |term: &str| -> f64 { ((n as f64) / (1.0 + *df.get(term).unwrap_or(&0) as f64)).ln() };
+
+    let mut uncategorized = Vec::new();
+    let mut clusters: Vec<SemanticCluster> = Vec::new();
+
+    for (idx, tokens) in tokens_per_message.iter().enumerate() {
+        if messages[idx].text.trim().is_empty() || tokens.is_empty() {
+            uncategorized.push(idx);
+            continue;
+        }
+
+        let total_tokens = tokens.len() as f64;
+        let mut counts: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+        for term in tokens {
+            *counts.entry(term.clone()).or_insert(0.0) += 1.0;
+        }
+        let raw: SparseVector = counts
+            .into_iter()
+            .map(|(term, count)| {
+                let weight = (count / total_tokens) * idf(&term);
+                (term, weight)
+            })
+            .collect();
+        let vector = l2_normalize(raw);
+
+        if norm(&vector) == 0.0 {
+            uncategorized.push(idx);
+            continue;
+        }
+
+        let mut best: Option<(usize, f64)> = None;
+        for (cluster_idx, cluster) in clusters.iter().enumerate() {
+            let sim = cosine_similarity(&vector, &cluster.sum_vector);
+            if best.is_none_or(|(_, best_sim)| sim > best_sim) {
+                best = Some((cluster_idx, sim));
+            }
+        }
+
+        match best {
+            Some((cluster_idx, sim)) if sim >= threshold => {
+                let cluster = &mut clusters[cluster_idx];
+                for (term, weight) in &vector {
+                    *cluster.sum_vector.entry(term.clone()).or_insert(0.0) += weight;
+                }
+                cluster.member_indices.push(idx);
+            }
+            _ => clusters.push(SemanticCluster {
+                sum_vector: vector,
+                member_indices: vec![idx],
+            }),
+        }
+    }
+
+    let mut buckets: Vec<ClusterBucket> = clusters
+        .iter()
+        .map(|cluster| {
+            let mut terms: Vec<(&String, &f64)> = cluster.sum_vector.iter().collect();
+            terms.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal));
+            let label = terms
+                .iter()
+                .take(3)
+                .map(|(term, _)| term.as_str())
+                .collect::<Vec<_>>()
+                .join(" / ");
+            let label = if label.is_empty() { "Uncategorized".to_string() } else { label };
+
+            let texts: Vec<String> = cluster
+                .member_indices
+                .iter()
+                .map(|&idx| messages[idx].text.clone())
+                .collect();
+
+            build_bucket(label, texts, compute_dedup, lexicon)
+        })
+        .collect();
+
+    if !uncategorized.is_empty() {
+        let texts: Vec<String> = uncategorized.iter().map(|&idx| messages[idx].text.clone()).collect();
+        buckets.push(build_bucket("Uncategorized".to_string(), texts, compute_dedup, lexicon));
+    }
+
+    ClusterResult {
+        buckets,
+        processed_count: messages.len(),
+    }
+}
+
+/// A single window of chat activity whose message count was statistically
+/// unusual relative to the windows before it.
+#[derive(Serialize, Deserialize)]
+pub struct Spike {
+    pub window_start: f64,
+    pub window_end: f64,
+    pub count: usize,
+    pub z_score: f64,
+    pub sample_messages: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BurstResult {
+    pub spikes: Vec<Spike>,
+}
+
+/// Minimum number of prior windows required before z-scoring kicks in, to
+/// avoid flagging spikes against too little history.
+const MIN_HISTORY_WINDOWS: usize = 3;
+
+/// Upper bound on the number of time windows `detect_bursts` will allocate.
+/// A `window_ms` that's small relative to the message timestamp span (e.g.
+/// passed in the wrong unit) would otherwise produce hundreds of millions of
+/// empty windows and a multi-GB allocation from ordinary bad input.
+const MAX_BURST_WINDOWS: usize = 1_000_000;
+
+/// Detects bursts of chat activity by binning messages into fixed-size time
+/// windows and flagging any window whose count is a statistical outlier
+/// relative to the windows before it.
+///
+/// Messages are binned by `timestamp` into windows of `window_ms`, starting
+/// at the earliest message's timestamp. For each window, a running mean and
+/// standard deviation is computed over the *preceding* windows only, and the
+/// window is flagged as a [`Spike`] when its count exceeds
+/// `mean + z_threshold * stddev` (default `z_threshold` is 2.0). Windows with
+/// fewer than 3 preceding windows of history are never flagged (cold start).
+///
+/// To detect bursts within a single cluster bucket rather than overall chat
+/// volume, filter `messages` down to that bucket's members before calling
+/// this function.
+///
+/// Errors if `window_ms` is too small relative to the span between the
+/// earliest and latest message timestamp: binning would require more than
+/// `MAX_BURST_WINDOWS` windows, which would otherwise allocate hundreds of
+/// megabytes or more for a single call.
+#[wasm_bindgen]
+pub fn detect_bursts(
+    messages_json: JsValue,
+    window_ms: f64,
+    z_threshold: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    let messages: Vec<Message> = serde_wasm_bindgen::from_value(messages_json)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    let result = detect_bursts_internal(&messages, window_ms, z_threshold.unwrap_or(2.0))
+        .map_err(|e| JsValue::from_str(&e))?;
+
     serde_wasm_bindgen::to_value(&result)
         .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
 }
 
+fn detect_bursts_internal(
+    messages: &[Message],
+    window_ms: f64,
+    z_threshold: f64,
+) -> Result<BurstResult, String> {
+    if messages.is_empty() || window_ms <= 0.0 {
+        return Ok(BurstResult { spikes: Vec::new() });
+    }
+
+    let min_timestamp = messages
+        .iter()
+        .map(|m| m.timestamp)
+        .fold(f64::INFINITY, f64::min);
+    let max_timestamp = messages
+        .iter()
+        .map(|m| m.timestamp)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    // `as usize` saturates on huge spans; saturating_add keeps that from
+    // overflowing on 32-bit targets (wasm32) so the check below still errors.
+    let num_windows = (((max_timestamp - min_timestamp) / window_ms).floor() as usize).saturating_add(1);
+    if num_windows > MAX_BURST_WINDOWS {
+        return Err(format!(
+            "window_ms {} is too small for the given timestamp span: would require {} windows (max {})",
+            window_ms, num_windows, MAX_BURST_WINDOWS
+        ));
+    }
+    let mut windows: Vec<Vec<usize>> = vec![Vec::new(); num_windows];
+
+    for (idx, msg) in messages.iter().enumerate() {
+        let window_idx = ((msg.timestamp - min_timestamp) / window_ms).floor() as usize;
+        windows[window_idx.min(num_windows - 1)].push(idx);
+    }
+
+    let mut spikes = Vec::new();
+    let mut prior_counts: Vec<f64> = Vec::new();
+
+    for (window_idx, members) in windows.iter().enumerate() {
+        let count = members.len();
+
+        if prior_counts.len() >= MIN_HISTORY_WINDOWS {
+            let mean = prior_counts.iter().sum::<f64>() / prior_counts.len() as f64;
+            let variance = prior_counts.iter().map(|c| (c - mean).powi(2)).sum::<f64>()
+                / prior_counts.len() as f64;
+            let stddev = variance.sqrt();
+
+            let z_score = if stddev > 0.0 {
+                (count as f64 - mean) / stddev
+            } else if count as f64 > mean {
+                f64::INFINITY
+            } else {
+                0.0
+            };
+
+            if z_score > z_threshold {
+                let window_start = min_timestamp + (window_idx as f64) * window_ms;
+                spikes.push(Spike {
+                    window_start,
+                    window_end: window_start + window_ms,
+                    count,
+                    z_score,
+                    sample_messages: members
+                        .iter()
+                        .take(3)
+                        .map(|&idx| messages[idx].text.clone())
+                        .collect(),
+                });
+            }
+        }
+
+        prior_counts.push(count as f64);
+    }
+
+    Ok(BurstResult { spikes })
+}
+
+/// Number of MinHash seeds used per message signature; higher means a more
+/// precise Jaccard estimate at the cost of more hashing.
+const MINHASH_SEEDS: usize = 64;
+
+/// Default estimated-Jaccard-similarity threshold above which two messages
+/// are treated as near-duplicates.
+const DEFAULT_DEDUP_THRESHOLD: f64 = 0.8;
+
+/// Breaks a message's text into overlapping shingles: 3-word shingles for
+/// messages with at least 3 words, falling back to 5-character shingles (or
+/// the whole lowercased text, if even shorter) for very short messages.
+fn shingles(text: &str) -> std::collections::HashSet<String> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+
+    if words.len() >= 3 {
+        return words.windows(3).map(|w| w.join(" ")).collect();
+    }
+
+    let chars: Vec<char> = lower.chars().collect();
+    if chars.len() >= 5 {
+        chars.windows(5).map(|c| c.iter().collect()).collect()
+    } else {
+        std::iter::once(lower).collect()
+    }
+}
+
+fn hash_shingle(shingle: &str, seed: u64) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    seed.hash(&mut hasher);
+    shingle.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A MinHash signature: the minimum shingle hash seen under each of
+/// `MINHASH_SEEDS` independent seeds, used to estimate Jaccard similarity
+/// without comparing full shingle sets.
+fn minhash_signature(shingles: &std::collections::HashSet<String>) -> Vec<u64> {
+    (0..MINHASH_SEEDS as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|s| hash_shingle(s, seed))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn estimate_jaccard(signature_a: &[u64], signature_b: &[u64]) -> f64 {
+    let matches = signature_a
+        .iter()
+        .zip(signature_b.iter())
+        .filter(|(a, b)| a == b)
+        .count();
+    matches as f64 / signature_a.len() as f64
+}
+
+/// Minimal union-find used to collapse near-duplicate messages into classes.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parent: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]);
+        }
+        self.parent[x]
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a != root_b {
+            self.parent[root_a] = root_b;
+        }
+    }
+}
+
+/// Groups near-duplicate messages by estimated MinHash Jaccard similarity.
+/// Returns one group id per input text (the root index of its union-find
+/// class); two texts share a group id if and only if they were chained
+/// together by a similarity >= `similarity_threshold`.
+fn group_duplicates(texts: &[String], similarity_threshold: f64) -> Vec<usize> {
+    let signatures: Vec<Vec<u64>> = texts.iter().map(|t| minhash_signature(&shingles(t))).collect();
+
+    let mut uf = UnionFind::new(texts.len());
+    for i in 0..texts.len() {
+        for j in (i + 1)..texts.len() {
+            if estimate_jaccard(&signatures[i], &signatures[j]) >= similarity_threshold {
+                uf.union(i, j);
+            }
+        }
+    }
+
+    (0..texts.len()).map(|i| uf.find(i)).collect()
+}
+
+/// Builds a `ClusterBucket` from a bucket's raw member texts.
+///
+/// `compute_dedup` gates the MinHash near-duplicate pass that populates
+/// `unique_count`: it's O(n^2) in the bucket's member count, so callers that
+/// don't need it (the common case) get `unique_count == count` at O(1) cost
+/// instead of paying for a full pairwise comparison on every bucket.
+///
+/// `lexicon` drives `sentiment_score`/`positive_count`/`negative_count`;
+/// callers share one instance across buckets rather than rebuilding the
+/// table per call.
+fn build_bucket(label: String, texts: Vec<String>, compute_dedup: bool, lexicon: &Lexicon) -> ClusterBucket {
+    let unique_count = if compute_dedup {
+        let groups = group_duplicates(&texts, DEFAULT_DEDUP_THRESHOLD);
+        groups.iter().collect::<std::collections::HashSet<_>>().len()
+    } else {
+        texts.len()
+    };
+
+    let scores: Vec<f64> = texts.iter().map(|t| message_sentiment_score(t, lexicon)).collect();
+    let sentiment_score = if scores.is_empty() {
+        0.0
+    } else {
+        scores.iter().sum::<f64>() / scores.len() as f64
+    };
+    let positive_count = scores.iter().filter(|&&s| s > 0.0).count();
+    let negative_count = scores.iter().filter(|&&s| s < 0.0).count();
+    let toxic_count = texts.iter().filter(|t| is_toxic(&t.to_lowercase())).count();
+
+    ClusterBucket {
+        label,
+        count: texts.len(),
+        unique_count,
+        sentiment_score,
+        positive_count,
+        negative_count,
+        toxic_count,
+        sample_messages: texts.into_iter().take(3).collect(),
+    }
+}
+
+/// One class of near-duplicate messages, collapsed to a single representative.
+#[derive(Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub representative: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpamReport {
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    pub top_repeated_messages: Vec<String>,
+}
+
+/// Detects copy-pasted spam and emote floods via MinHash shingling, without
+/// requiring exact text matches.
+///
+/// Messages are grouped into near-duplicate classes (estimated Jaccard
+/// similarity >= `similarity_threshold`, default 0.8) using 3-word (or
+/// 5-character, for short messages) shingles and 64-seed MinHash signatures.
+/// Only classes with 2 or more members are reported as `duplicate_groups`;
+/// each keeps one representative message and its total count.
+/// `top_repeated_messages` holds up to 5 representatives, ordered by
+/// descending group size.
+#[wasm_bindgen]
+pub fn detect_spam(
+    messages_json: JsValue,
+    similarity_threshold: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    let messages: Vec<Message> = serde_wasm_bindgen::from_value(messages_json)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    let result = detect_spam_internal(
+        &messages,
+        similarity_threshold.unwrap_or(DEFAULT_DEDUP_THRESHOLD),
+    );
+
+    serde_wasm_bindgen::to_value(&result)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+fn detect_spam_internal(messages: &[Message], similarity_threshold: f64) -> SpamReport {
+    let texts: Vec<String> = messages.iter().map(|m| m.text.clone()).collect();
+    let groups = group_duplicates(&texts, similarity_threshold);
+
+    let mut members_by_group: std::collections::HashMap<usize, Vec<usize>> =
+        std::collections::HashMap::new();
+    for (idx, &group_id) in groups.iter().enumerate() {
+        members_by_group.entry(group_id).or_default().push(idx);
+    }
+
+    let mut duplicate_groups: Vec<DuplicateGroup> = members_by_group
+        .values()
+        .filter(|members| members.len() > 1)
+        .map(|members| DuplicateGroup {
+            representative: texts[members[0]].clone(),
+            count: members.len(),
+        })
+        .collect();
+    duplicate_groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+
+    let top_repeated_messages = duplicate_groups
+        .iter()
+        .take(5)
+        .map(|g| g.representative.clone())
+        .collect();
+
+    SpamReport {
+        duplicate_groups,
+        top_repeated_messages,
+    }
+}
+
+/// Term-to-weight polarity table used for lexicon-based sentiment scoring.
+/// Positive weights push a message's score up, negative weights pull it down.
+type Lexicon = std::collections::HashMap<String, f64>;
+
+/// Small embedded word-polarity table covering common chat sentiment terms.
+/// Callers of [`score_sentiment`] may supply their own table instead.
+fn default_lexicon() -> Lexicon {
+    [
+        ("love", 1.0),
+        ("great", 1.0),
+        ("awesome", 1.0),
+        ("amazing", 1.0),
+        ("excellent", 1.0),
+        ("thanks", 0.8),
+        ("thank", 0.8),
+        ("happy", 0.8),
+        ("good", 0.7),
+        ("nice", 0.6),
+        ("please", 0.3),
+        ("sad", -0.6),
+        ("annoying", -0.6),
+        ("broken", -0.6),
+        ("angry", -0.7),
+        ("bad", -0.7),
+        ("stupid", -0.8),
+        ("hate", -1.0),
+        ("terrible", -1.0),
+        ("awful", -1.0),
+        ("worst", -1.0),
+    ]
+    .into_iter()
+    .map(|(term, weight)| (term.to_string(), weight))
+    .collect()
+}
+
+/// Small profanity term set driving [`MessageSentiment::toxicity_flag`]. Kept
+/// deliberately narrow; moderators can layer their own bucket rules (see
+/// [`ClusterRule`]) on top for community-specific terms.
+const TOXIC_TERMS: &[&str] = &["idiot", "moron", "stupid", "shut up", "trash", "garbage"];
+
+fn is_toxic(text_lower: &str) -> bool {
+    TOXIC_TERMS.iter().any(|term| text_lower.contains(term))
+}
+
+/// Sums matched lexicon weights over a message's tokens, normalized by total
+/// token count (not just matched tokens), and clamps the result to [-1, 1].
+fn message_sentiment_score(text: &str, lexicon: &Lexicon) -> f64 {
+    let tokens = tokenize(text);
+    if tokens.is_empty() {
+        return 0.0;
+    }
+
+    let sum: f64 = tokens.iter().filter_map(|t| lexicon.get(t)).sum();
+    (sum / tokens.len() as f64).clamp(-1.0, 1.0)
+}
+
+/// Per-message sentiment and toxicity, as returned by [`score_sentiment`].
+#[derive(Serialize, Deserialize)]
+pub struct MessageSentiment {
+    pub text: String,
+    pub sentiment_score: f64,
+    pub toxicity_flag: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SentimentReport {
+    pub messages: Vec<MessageSentiment>,
+}
+
+/// Scores each message's sentiment against a lexicon and flags likely
+/// toxicity, fully offline so it still runs inside WASM with no network
+/// calls.
+///
+/// When `lexicon_json` is `undefined`/`null`, the small embedded
+/// word-polarity table is used; otherwise it's replaced entirely by the
+/// caller-supplied `{ "term": weight, ... }` map. `cluster_messages` and
+/// `cluster_messages_semantic` accept the same override for the
+/// `sentiment_score`/`positive_count`/`negative_count` fields they compute
+/// per bucket. The lexicon never affects `toxicity_flag` or `toxic_count`,
+/// which come from a fixed toxic term set.
+#[wasm_bindgen]
+pub fn score_sentiment(messages_json: JsValue, lexicon_json: JsValue) -> Result<JsValue, JsValue> {
+    let messages: Vec<Message> = serde_wasm_bindgen::from_value(messages_json)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    let lexicon: Lexicon = if lexicon_json.is_undefined() || lexicon_json.is_null() {
+        default_lexicon()
+    } else {
+        serde_wasm_bindgen::from_value(lexicon_json)
+            .map_err(|e| JsValue::from_str(&format!("Lexicon parse error: {}", e)))?
+    };
+
+    let result = score_sentiment_internal(&messages, &lexicon);
+
+    serde_wasm_bindgen::to_value(&result)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+fn score_sentiment_internal(messages: &[Message], lexicon: &Lexicon) -> SentimentReport {
+    let messages = messages
+        .iter()
+        .map(|m| MessageSentiment {
+            text: m.text.clone(),
+            sentiment_score: message_sentiment_score(&m.text, lexicon),
+            toxicity_flag: is_toxic(&m.text.to_lowercase()),
+        })
+        .collect();
+
+    SentimentReport { messages }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,64 +1049,353 @@ mod tests {
         assert!(result.buckets.iter().find(|b| b.label == "Requests").is_none());
     }
 
-    // Internal function for testing (not exposed to WASM)
-    fn cluster_messages_internal(messages: &[Message]) -> ClusterResult {
-        let mut questions = Vec::new();
-        let mut issues = Vec::new();
-        let mut requests = Vec::new();
-        let mut general = Vec::new();
-
-        for msg in messages.iter() {
-            let text_lower = msg.text.to_lowercase();
-            
-            if text_lower.contains('?') || text_lower.contains("how ") || text_lower.contains("what ") || text_lower.contains("why ") {
-                questions.push(msg.text.clone());
-            } else if text_lower.contains("bug") || text_lower.contains("error") || text_lower.contains("broken") || text_lower.contains("issue") {
-                issues.push(msg.text.clone());
-            } else if text_lower.contains("please") || text_lower.contains("can you") || text_lower.contains("could you") || text_lower.contains("would you") {
-                requests.push(msg.text.clone());
-            } else {
-                general.push(msg.text.clone());
-            }
-        }
+    #[test]
+    fn test_semantic_clustering_groups_similar_topics() {
+        let messages = vec![
+            create_test_message("payment checkout failing error"),
+            create_test_message("payment checkout failing again"),
+            create_test_message("stream schedule today announcement"),
+            create_test_message("stream schedule announcement today"),
+        ];
 
-        let mut buckets = Vec::new();
+        let result = cluster_messages_semantic_internal(&messages, 0.3, false, &default_lexicon());
 
-        if !questions.is_empty() {
-            buckets.push(ClusterBucket {
-                label: "Questions".to_string(),
-                count: questions.len(),
-                sample_messages: questions.into_iter().take(3).collect(),
-            });
-        }
+        // Expect two topical clusters, not one big bucket and not four singletons.
+        assert_eq!(result.buckets.len(), 2);
+        assert!(result.buckets.iter().all(|b| b.count == 2));
+        assert_eq!(result.processed_count, 4);
+    }
+
+    #[test]
+    fn test_semantic_clustering_uncategorized_catch_all() {
+        let messages = vec![
+            create_test_message(""),
+            create_test_message("   "),
+            create_test_message("hello there friend"),
+        ];
 
-        if !issues.is_empty() {
-            buckets.push(ClusterBucket {
-                label: "Issues/Bugs".to_string(),
-                count: issues.len(),
-                sample_messages: issues.into_iter().take(3).collect(),
-            });
+        let result = cluster_messages_semantic_internal(&messages, 0.3, false, &default_lexicon());
+
+        let uncategorized = result.buckets.iter().find(|b| b.label == "Uncategorized");
+        assert!(uncategorized.is_some());
+        assert_eq!(uncategorized.unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_semantic_clustering_single_token_messages() {
+        let messages = vec![
+            create_test_message("bug"),
+            create_test_message("completely unrelated filler text"),
+            create_test_message("yet another unrelated filler message"),
+        ];
+
+        let result = cluster_messages_semantic_internal(&messages, 0.3, false, &default_lexicon());
+
+        // Single-token messages still form valid unit vectors and get clustered,
+        // not dumped into Uncategorized.
+        assert_eq!(result.processed_count, 3);
+        assert!(result.buckets.iter().find(|b| b.label == "Uncategorized").is_none());
+    }
+
+    fn test_rule(label: &str, keywords: &[&str], regex: Option<&str>, priority: i32) -> ClusterRule {
+        ClusterRule {
+            label: label.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            regex: regex.map(|r| r.to_string()),
+            priority,
         }
+    }
+
+    #[test]
+    fn test_custom_rules_first_match_wins() {
+        let messages = vec![
+            create_test_message("this giveaway is a scam"),
+            create_test_message("mods please help, scam in chat"),
+            create_test_message("just chatting"),
+        ];
+        let config = ClusterConfig {
+            rules: vec![
+                test_rule("Scam Alert", &["scam"], None, 10),
+                test_rule("Requests", &["please"], None, 5),
+            ],
+            multi_label: false,
+        };
+
+        let result = cluster_messages_with_rules(&messages, &config, false, &default_lexicon()).unwrap();
+
+        // Higher-priority "Scam Alert" wins both scam messages, even the one
+        // that also contains "please".
+        let scam_bucket = result.buckets.iter().find(|b| b.label == "Scam Alert").unwrap();
+        assert_eq!(scam_bucket.count, 2);
+        assert!(result.buckets.iter().find(|b| b.label == "Requests").is_none());
+        assert_eq!(result.processed_count, 3);
+    }
+
+    #[test]
+    fn test_custom_rules_multi_label() {
+        let messages = vec![create_test_message("this giveaway is a scam, please ban them")];
+        let config = ClusterConfig {
+            rules: vec![
+                test_rule("Scam Alert", &["scam"], None, 10),
+                test_rule("Requests", &["please"], None, 5),
+            ],
+            multi_label: true,
+        };
+
+        let result = cluster_messages_with_rules(&messages, &config, false, &default_lexicon()).unwrap();
+
+        assert_eq!(result.buckets.len(), 2);
+        assert!(result.buckets.iter().all(|b| b.count == 1));
+        assert_eq!(result.processed_count, 1);
+    }
+
+    #[test]
+    fn test_custom_rules_regex() {
+        let messages = vec![
+            create_test_message("error code 42 on login"),
+            create_test_message("everything is fine"),
+        ];
+        let config = ClusterConfig {
+            rules: vec![test_rule("Error Codes", &[], Some(r"error code \d+"), 1)],
+            multi_label: false,
+        };
+
+        let result = cluster_messages_with_rules(&messages, &config, false, &default_lexicon()).unwrap();
+
+        let bucket = result.buckets.iter().find(|b| b.label == "Error Codes").unwrap();
+        assert_eq!(bucket.count, 1);
+    }
+
+    #[test]
+    fn test_custom_rules_regex_is_case_sensitive() {
+        let messages = vec![
+            create_test_message("HELLO WORLD"),
+            create_test_message("hello world"),
+        ];
+        let config = ClusterConfig {
+            rules: vec![test_rule("Shouting", &[], Some(r"^[A-Z\s]+$"), 1)],
+            multi_label: false,
+        };
 
-        if !requests.is_empty() {
-            buckets.push(ClusterBucket {
-                label: "Requests".to_string(),
-                count: requests.len(),
-                sample_messages: requests.into_iter().take(3).collect(),
-            });
+        let result = cluster_messages_with_rules(&messages, &config, false, &default_lexicon()).unwrap();
+
+        let bucket = result.buckets.iter().find(|b| b.label == "Shouting").unwrap();
+        assert_eq!(bucket.count, 1);
+    }
+
+    #[test]
+    fn test_custom_rules_invalid_regex_errors() {
+        let messages = vec![create_test_message("hello")];
+        let config = ClusterConfig {
+            rules: vec![test_rule("Broken", &[], Some("(unclosed"), 1)],
+            multi_label: false,
+        };
+
+        assert!(cluster_messages_with_rules(&messages, &config, false, &default_lexicon()).is_err());
+    }
+
+    #[test]
+    fn test_no_config_falls_back_to_default_buckets() {
+        let messages = vec![
+            create_test_message("How do I do this?"),
+            create_test_message("Just a regular message"),
+        ];
+
+        let result = cluster_messages_default(&messages, false, &default_lexicon());
+
+        assert!(result.buckets.iter().any(|b| b.label == "Questions"));
+        assert!(result.buckets.iter().any(|b| b.label == "General Chat"));
+    }
+
+    fn create_timed_message(text: &str, timestamp: f64) -> Message {
+        Message {
+            text: text.to_string(),
+            author: "TestUser".to_string(),
+            timestamp,
         }
+    }
 
-        if !general.is_empty() {
-            buckets.push(ClusterBucket {
-                label: "General Chat".to_string(),
-                count: general.len(),
-                sample_messages: general.into_iter().take(3).collect(),
-            });
+    #[test]
+    fn test_detect_bursts_flags_spike_after_quiet_baseline() {
+        let mut messages = vec![
+            create_timed_message("hi", 0.0),
+            create_timed_message("hi", 1000.0),
+            create_timed_message("hi", 2000.0),
+            create_timed_message("hi", 3000.0),
+        ];
+        for i in 0..10 {
+            messages.push(create_timed_message("raid incoming", 4000.0 + i as f64 * 50.0));
         }
 
-        ClusterResult {
-            buckets,
-            processed_count: messages.len(),
+        let result = detect_bursts_internal(&messages, 1000.0, 2.0).unwrap();
+
+        assert_eq!(result.spikes.len(), 1);
+        let spike = &result.spikes[0];
+        assert_eq!(spike.count, 10);
+        assert_eq!(spike.window_start, 4000.0);
+        assert!(spike.sample_messages.len() <= 3);
+    }
+
+    #[test]
+    fn test_detect_bursts_cold_start_skips_early_windows() {
+        // Only 2 windows total, so there's never enough history (3 prior
+        // windows) to z-score against, even though the second window is a
+        // huge spike relative to the first.
+        let mut messages = vec![create_timed_message("hi", 0.0)];
+        for i in 0..50 {
+            messages.push(create_timed_message("raid", 1000.0 + i as f64));
         }
+
+        let result = detect_bursts_internal(&messages, 1000.0, 2.0).unwrap();
+
+        assert!(result.spikes.is_empty());
+    }
+
+    #[test]
+    fn test_detect_bursts_empty_input() {
+        let result = detect_bursts_internal(&[], 1000.0, 2.0).unwrap();
+        assert!(result.spikes.is_empty());
+    }
+
+    #[test]
+    fn test_detect_bursts_rejects_window_ms_too_small_for_span() {
+        // A 30-day span with a 0.001 ms window would otherwise require about
+        // 2.6 trillion windows.
+        let messages = vec![
+            create_timed_message("hi", 0.0),
+            create_timed_message("bye", 1000.0 * 60.0 * 60.0 * 24.0 * 30.0),
+        ];
+
+        let result = detect_bursts_internal(&messages, 0.001, 2.0);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_detect_spam_collapses_near_duplicates() {
+        let base = "welcome to the stream everyone glad you could make it today";
+        let messages = vec![
+            create_test_message(base),
+            create_test_message(&format!("{} friend", base)),
+            create_test_message(&format!("{} thanks", base)),
+            create_test_message("let's talk about something totally different folks"),
+        ];
+
+        let result = detect_spam_internal(&messages, 0.8);
+
+        assert_eq!(result.duplicate_groups.len(), 1);
+        assert_eq!(result.duplicate_groups[0].count, 3);
+        assert_eq!(result.top_repeated_messages.len(), 1);
+    }
+
+    #[test]
+    fn test_detect_spam_no_duplicates() {
+        let messages = vec![
+            create_test_message("hello everyone"),
+            create_test_message("how is the weather today"),
+            create_test_message("great stream as always"),
+        ];
+
+        let result = detect_spam_internal(&messages, 0.8);
+
+        assert!(result.duplicate_groups.is_empty());
+        assert!(result.top_repeated_messages.is_empty());
+    }
+
+    #[test]
+    fn test_build_bucket_unique_count_collapses_duplicates() {
+        let base = "welcome to the stream everyone glad you could make it today";
+        let texts = vec![
+            base.to_string(),
+            format!("{} friend", base),
+            "let's talk about something totally different folks".to_string(),
+        ];
+
+        let bucket = build_bucket("General Chat".to_string(), texts, true, &default_lexicon());
+
+        assert_eq!(bucket.count, 3);
+        assert_eq!(bucket.unique_count, 2);
+    }
+
+    #[test]
+    fn test_message_sentiment_score_positive_and_negative() {
+        let lexicon = default_lexicon();
+
+        let positive = message_sentiment_score("this stream is great and awesome", &lexicon);
+        assert!(positive > 0.0, "expected positive score, got {}", positive);
+
+        let negative = message_sentiment_score("this is terrible and broken", &lexicon);
+        assert!(negative < 0.0, "expected negative score, got {}", negative);
+
+        let neutral = message_sentiment_score("what time is it", &lexicon);
+        assert_eq!(neutral, 0.0);
+    }
+
+    #[test]
+    fn test_message_sentiment_score_clamped() {
+        let mut lexicon = Lexicon::new();
+        lexicon.insert("hype".to_string(), 5.0);
+        // Unclamped, "hype hype stream" scores (5.0 + 5.0) / 3 tokens = 3.33;
+        // it should clamp at 1.0 rather than exceed it.
+        let score = message_sentiment_score("hype hype stream", &lexicon);
+        assert_eq!(score, 1.0);
+    }
+
+    #[test]
+    fn test_score_sentiment_internal_flags_toxicity() {
+        let messages = vec![
+            create_test_message("you are an idiot"),
+            create_test_message("thanks so much for the help"),
+        ];
+        let lexicon = default_lexicon();
+
+        let result = score_sentiment_internal(&messages, &lexicon);
+
+        assert!(result.messages[0].toxicity_flag);
+        assert!(!result.messages[1].toxicity_flag);
+        assert!(result.messages[1].sentiment_score > 0.0);
+    }
+
+    #[test]
+    fn test_score_sentiment_internal_custom_lexicon_override() {
+        let messages = vec![create_test_message("this game is so cringe")];
+        let mut lexicon = Lexicon::new();
+        lexicon.insert("cringe".to_string(), -1.0);
+
+        let result = score_sentiment_internal(&messages, &lexicon);
+
+        assert!(result.messages[0].sentiment_score < 0.0);
+    }
+
+    #[test]
+    fn test_build_bucket_reports_sentiment_aggregates() {
+        let texts = vec![
+            "this is great and awesome".to_string(),
+            "this is terrible and broken".to_string(),
+            "what time is it".to_string(),
+        ];
+
+        let bucket = build_bucket("General Chat".to_string(), texts, false, &default_lexicon());
+
+        assert_eq!(bucket.positive_count, 1);
+        assert_eq!(bucket.negative_count, 1);
+    }
+
+    #[test]
+    fn test_build_bucket_honors_custom_lexicon() {
+        let texts = vec!["this game is so cringe".to_string()];
+        let mut lexicon = Lexicon::new();
+        lexicon.insert("cringe".to_string(), -1.0);
+
+        let bucket = build_bucket("General Chat".to_string(), texts, false, &lexicon);
+
+        assert_eq!(bucket.negative_count, 1);
+        assert!(bucket.sentiment_score < 0.0);
+    }
+
+    // cluster_messages's v0 logic now lives in cluster_messages_default, which
+    // (unlike the #[wasm_bindgen] entry point) tests can call directly.
+    fn cluster_messages_internal(messages: &[Message]) -> ClusterResult {
+        cluster_messages_default(messages, false, &default_lexicon())
     }
 }